@@ -8,8 +8,9 @@ use std::{
 use bevy_app::prelude::*;
 use bevy_ecs::{prelude::*, query::QueryFilter};
 use bevy_hierarchy::Parent;
-use bevy_math::IVec3;
+use bevy_math::{IVec3, Vec3};
 use bevy_reflect::Reflect;
+use bevy_transform::prelude::Transform;
 use bevy_utils::{
     hashbrown::{HashMap, HashSet},
     AHasher, PassHash,
@@ -24,38 +25,133 @@ use crate::{precision::GridPrecision, GridCell};
 /// match the supplied query filter. This is useful if you only want to, say, compute hashes and
 /// insert in the [`SpatialHashMap`] for `Player` entities. If you are adding multiple copies of
 /// this plugin, take care not to overlap the queries to avoid duplicating work.
-#[derive(Default)]
-pub struct SpatialHashPlugin<P: GridPrecision, F: QueryFilter = ()>(PhantomData<(P, F)>);
+///
+/// By default, this also emits a [`CellChanged`] event whenever an entity's [`SpatialHash`]
+/// changes, so gameplay systems can react to cell transitions (streaming chunk load/unload,
+/// trigger volumes, authority handoff, etc.) without polling every entity every frame. Use
+/// [`Self::without_events`] to disable this if you don't need it.
+///
+/// With the `sharded_map` feature enabled, [`SpatialHashMap`] splits its cells across multiple
+/// shards and rebuilds them in parallel every frame, instead of rebuilding a single map
+/// sequentially. This is only worth the overhead for large, streaming worlds where tens of
+/// thousands of entities change cells every frame; small worlds should leave the feature off.
+pub struct SpatialHashPlugin<P: GridPrecision, F: QueryFilter = ()> {
+    send_events: bool,
+    spooky: PhantomData<(P, F)>,
+}
+
+impl<P: GridPrecision, F: QueryFilter> Default for SpatialHashPlugin<P, F> {
+    fn default() -> Self {
+        Self {
+            send_events: true,
+            spooky: PhantomData,
+        }
+    }
+}
+
+impl<P: GridPrecision, F: QueryFilter> SpatialHashPlugin<P, F> {
+    /// Disable the [`CellChanged`] event channel. Use this if you don't react to cell transitions,
+    /// to skip writing an event for every entity that changes cells.
+    pub fn without_events(mut self) -> Self {
+        self.send_events = false;
+        self
+    }
+}
 
 impl<P: GridPrecision, F: QueryFilter + Send + Sync + 'static> Plugin for SpatialHashPlugin<P, F> {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SpatialHashMap<P, F>>().add_systems(
+        app.insert_resource(SpatialHashMap::<P, F> {
+            send_events: self.send_events,
+            ..Default::default()
+        })
+        .add_event::<CellChanged<P>>();
+
+        #[cfg(not(feature = "sharded_map"))]
+        app.add_systems(
             PostUpdate,
             SpatialHashMap::<P, F>::update_spatial_hash
                 .after(crate::FloatingOriginSet::RecenterLargeTransforms)
                 .in_set(bevy_transform::TransformSystem::TransformPropagate),
         );
+        #[cfg(feature = "sharded_map")]
+        app.add_systems(
+            PostUpdate,
+            SpatialHashMap::<P, F>::update_spatial_hash_sharded
+                .after(crate::FloatingOriginSet::RecenterLargeTransforms)
+                .in_set(bevy_transform::TransformSystem::TransformPropagate),
+        );
     }
 }
 
+/// The number of shards [`SpatialHashMap`] splits its cells across.
+///
+/// With the `sharded_map` feature disabled, this is `1`, which keeps the original single-map fast
+/// path for small worlds. With it enabled, the map is split into fixed shards selected by the low
+/// bits of the [`SpatialHash`], so that a parallel rebuild can insert into different shards
+/// concurrently instead of contending on one `HashMap`. This must be a power of two.
+#[cfg(not(feature = "sharded_map"))]
+const SHARD_COUNT: usize = 1;
+#[cfg(feature = "sharded_map")]
+const SHARD_COUNT: usize = 16;
+
+type Shard<P> = HashMap<SpatialHash<P>, Vec<CellEntry<P>>, PassHash>;
+
 /// A global spatial hash map for quickly finding entities in a grid cell.
 #[derive(Resource)]
 pub struct SpatialHashMap<P: GridPrecision, F: QueryFilter = ()> {
-    map: HashMap<SpatialHash<P>, HashSet<Entity, PassHash>, PassHash>,
+    // Each hash bucket holds a small `Vec`, almost always of length 1. It only grows past 1 when
+    // two distinct `(Parent, GridCell)` keys happen to hash to the same `u64`, so that a collision
+    // never silently merges entities from different cells together.
+    shards: Vec<Shard<P>>,
     reverse_map: HashMap<Entity, SpatialHash<P>, PassHash>,
+    send_events: bool,
     spooky: PhantomData<F>,
 }
 
+/// The contents of a single occupied cell in a [`SpatialHashMap`].
+///
+/// In addition to the set of entities in the cell, this keeps the [`GridCell`] and [`Parent`] that
+/// produced the hash, so the cell can be re-expanded into its neighbors without needing to look
+/// anything up on the entities themselves, and so a hash collision with another cell can be
+/// detected by comparing this verified key. The local [`Transform`] translation of each entity is
+/// also kept, so queries can exactly refine the coarse, cell-granularity results of [`SpatialHashMap::neighbors`]
+/// down to a precise world-space region.
+struct CellEntry<P: GridPrecision> {
+    entities: HashSet<Entity, PassHash>,
+    translations: HashMap<Entity, Vec3, PassHash>,
+    cell: GridCell<P>,
+    parent: Entity,
+}
+
 impl<P: GridPrecision, F: QueryFilter> Default for SpatialHashMap<P, F> {
     fn default() -> Self {
         Self {
-            map: Default::default(),
+            shards: (0..SHARD_COUNT).map(|_| Shard::default()).collect(),
             reverse_map: Default::default(),
+            send_events: true,
             spooky: PhantomData,
         }
     }
 }
 
+/// An event that is sent whenever an entity's [`SpatialHash`] changes, i.e. it moves from one
+/// occupied cell into another (or is observed for the first time). Sent by
+/// [`SpatialHashMap::update_spatial_hash`], and can be disabled with
+/// [`SpatialHashPlugin::without_events`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CellChanged<P: GridPrecision> {
+    /// The entity that changed cells.
+    pub entity: Entity,
+    /// The entity's previous [`SpatialHash`], or `None` if it did not have one yet.
+    pub old: Option<SpatialHash<P>>,
+    /// The entity's new [`SpatialHash`].
+    pub new: SpatialHash<P>,
+    /// The entity's previous [`GridCell`] and parent, or `None` if it did not have one yet.
+    pub old_cell: Option<(GridCell<P>, Entity)>,
+    /// The entity's new [`GridCell`] and parent.
+    pub new_cell: (GridCell<P>, Entity),
+}
+
 /// An automatically updated `Component` that uniquely identifies an entity's cell.
 ///
 /// Once computed, a spatial hash can be used to rapidly check if any two entities are in the same
@@ -96,49 +192,206 @@ impl<P: GridPrecision> Hash for SpatialHash<P> {
 
 impl<P: GridPrecision> SpatialHash<P> {
     /// Generate a new hash from parts.
+    ///
+    /// Breaking change: this used to take `&Parent`; it now takes the parent's `Entity` directly
+    /// (e.g. `parent.get()`), since [`Parent`] is not `Copy`/`Clone` and could not be stored in
+    /// [`CellEntry`] or [`CellChanged`] by value. Callers should pass `parent.get()` instead of
+    /// `parent`.
     #[inline]
-    pub fn new(parent: &Parent, cell: &GridCell<P>) -> Self {
+    pub fn new(parent: Entity, cell: &GridCell<P>) -> Self {
         PartialSpatialHash::new(parent).generate(cell)
     }
 }
 
 impl<P: GridPrecision, F: QueryFilter> SpatialHashMap<P, F> {
-    fn insert(&mut self, entity: Entity, hash: SpatialHash<P>) {
+    /// The shard a given hash's bucket lives in. `SHARD_COUNT` is a power of two, so this is just
+    /// a mask of the hash's low bits.
+    #[inline]
+    fn shard_index(hash: SpatialHash<P>) -> usize {
+        hash.0 as usize & (SHARD_COUNT - 1)
+    }
+
+    #[inline]
+    fn shard(&self, hash: SpatialHash<P>) -> &Shard<P> {
+        &self.shards[Self::shard_index(hash)]
+    }
+
+    #[inline]
+    fn shard_mut(&mut self, hash: SpatialHash<P>) -> &mut Shard<P> {
+        &mut self.shards[Self::shard_index(hash)]
+    }
+
+    fn insert(
+        &mut self,
+        entity: Entity,
+        hash: SpatialHash<P>,
+        cell: GridCell<P>,
+        parent: Entity,
+        translation: Vec3,
+    ) {
         // If this entity is already in the maps, we need to remove and update it.
-        if let Some(old_hash) = self.reverse_map.get_mut(&entity) {
-            if hash.eq(old_hash) {
-                return; // If the spatial hash is unchanged, early exit.
+        if let Some(old_hash) = self.reverse_map.get(&entity).copied() {
+            if hash.eq(&old_hash) {
+                // The cell is unchanged, but the entity may have moved within it.
+                if let Some(entry) =
+                    self.shard_mut(old_hash).get_mut(&old_hash).and_then(|bucket| {
+                        bucket
+                            .iter_mut()
+                            .find(|entry| entry.parent == parent && entry.cell == cell)
+                    })
+                {
+                    entry.translations.insert(entity, translation);
+                }
+                return;
+            }
+            if let Some(bucket) = self.shard_mut(old_hash).get_mut(&old_hash) {
+                for entry in bucket.iter_mut() {
+                    entry.entities.remove(&entity);
+                    entry.translations.remove(&entity);
+                }
+                // Drop any entry that's now vacant, so it stops showing up as an occupied cell in
+                // `clusters`, `get_exact`, `iter`, etc.
+                bucket.retain(|entry| !entry.entities.is_empty());
             }
-            self.map
-                .get_mut(old_hash)
-                .map(|entities| entities.remove(&entity));
-            *old_hash = hash;
         }
+        self.reverse_map.insert(entity, hash);
 
-        self.map
-            .entry(hash)
-            .and_modify(|list| {
-                list.insert(entity);
-            })
-            .or_insert_with(|| {
-                let mut hm = HashSet::with_hasher(PassHash);
-                hm.insert(entity);
-                hm
+        let bucket = self.shard_mut(hash).entry(hash).or_default();
+        if let Some(entry) = bucket
+            .iter_mut()
+            .find(|entry| entry.parent == parent && entry.cell == cell)
+        {
+            entry.entities.insert(entity);
+            entry.translations.insert(entity, translation);
+        } else {
+            let mut entities = HashSet::with_hasher(PassHash);
+            entities.insert(entity);
+            let mut translations = HashMap::with_hasher(PassHash);
+            translations.insert(entity, translation);
+            bucket.push(CellEntry {
+                entities,
+                translations,
+                cell,
+                parent,
             });
+        }
     }
 
     /// Get a list of all entities in the same [`GridCell`] using a [`SpatialHash`].
+    ///
+    /// Because a [`SpatialHash`] is just a `u64`, two different cells can in rare cases hash to the
+    /// same value. This method does not check for that: if several occupied cells share `hash`, it
+    /// returns the entities of only the first of them stored under it, which may not be the cell you
+    /// are looking for. Use [`Self::get_exact`] if you need a verified, collision-free answer.
     #[inline]
     pub fn get(&self, hash: &SpatialHash<P>) -> Option<&HashSet<Entity, PassHash>> {
-        self.map.get(hash)
+        self.shard(*hash).get(hash)?.first().map(|entry| &entry.entities)
+    }
+
+    /// Get the entities in the exact cell identified by `parent` and `cell`.
+    ///
+    /// Unlike [`Self::get`], this verifies the full `(Parent, GridCell)` key after the hash lookup,
+    /// so a `u64` collision with an unrelated cell can never be mistaken for a match. Use this when
+    /// you need an authoritative "are these entities in this cell" answer, rather than a
+    /// conservative pre-filter. A vacated cell (no entities left in it) reads as `None`, not as an
+    /// empty set.
+    pub fn get_exact(&self, parent: Entity, cell: &GridCell<P>) -> Option<&HashSet<Entity, PassHash>> {
+        let hash = SpatialHash::new(parent, cell);
+        self.shard(hash)
+            .get(&hash)?
+            .iter()
+            .find(|entry| entry.parent == parent && entry.cell == *cell)
+            .map(|entry| &entry.entities)
+            .filter(|entities| !entities.is_empty())
     }
 
     /// An iterator visiting all spatial hash cells and their contents in arbitrary order.
     #[inline]
-    pub fn iter(
+    pub fn iter(&self) -> impl Iterator<Item = (SpatialHash<P>, &HashSet<Entity, PassHash>)> + '_ {
+        self.shards.iter().flat_map(|shard| shard.iter()).flat_map(
+            |(hash, bucket)| bucket.iter().map(move |entry| (*hash, &entry.entities)),
+        )
+    }
+
+    /// Partitions all occupied cells into connected components, where two cells are connected if
+    /// they are within a Chebyshev distance of `1` of each other (26-connectivity) and share the
+    /// same reference frame.
+    ///
+    /// Returns a list of clusters, each made up of the cells in that cluster and the union of all
+    /// entities occupying them. This is useful for things like AABB culling, territory detection,
+    /// or flood-fill gameplay, where you want to find groups of nearby occupied cells without
+    /// manually flooding from a known starting point.
+    pub fn clusters(&self) -> Vec<(Vec<SpatialHash<P>>, HashSet<Entity, PassHash>)> {
+        self.clusters_by(|_| ())
+    }
+
+    /// Like [`Self::clusters`], but two adjacent cells are only merged into the same cluster if
+    /// `group` returns the same value for an entity in each cell. This can be used to, for example,
+    /// only cluster cells occupied by the same faction.
+    ///
+    /// This assumes a single group per cell: a cell's group is taken from an arbitrary entity in it
+    /// (entities in the same cell are expected to share a group, e.g. allied units occupying the
+    /// same space). If a cell actually holds entities from multiple groups, the whole cell - and all
+    /// of its entities - is merged using whichever group happened to be sampled; it is not split.
+    pub fn clusters_by<G: Eq>(
         &self,
-    ) -> bevy_utils::hashbrown::hash_map::Iter<'_, SpatialHash<P>, HashSet<Entity, PassHash>> {
-        self.map.iter()
+        group: impl Fn(Entity) -> G,
+    ) -> Vec<(Vec<SpatialHash<P>>, HashSet<Entity, PassHash>)> {
+        let cell_group = |entry: &CellEntry<P>| entry.entities.iter().next().map(|&e| group(e));
+
+        let mut visited: HashSet<SpatialHash<P>> = HashSet::default();
+        let mut clusters = Vec::new();
+
+        let occupied_entry = |hash: SpatialHash<P>| {
+            self.shard(hash)
+                .get(&hash)
+                .and_then(|bucket| bucket.first())
+                .filter(|entry| !entry.entities.is_empty())
+        };
+
+        for &start in self.shards.iter().flat_map(|shard| shard.keys()) {
+            if visited.contains(&start) {
+                continue;
+            }
+            visited.insert(start);
+            // A vacated cell's empty entry can still linger in the map; skip it as a flood start so
+            // it doesn't show up as its own empty cluster.
+            if occupied_entry(start).is_none() {
+                continue;
+            }
+
+            let mut stack = vec![start];
+            let mut cells = Vec::new();
+            let mut entities = HashSet::with_hasher(PassHash);
+
+            while let Some(hash) = stack.pop() {
+                let Some(entry) = occupied_entry(hash) else {
+                    continue;
+                };
+                cells.push(hash);
+                entities.extend(entry.entities.iter().copied());
+
+                let this_group = cell_group(entry);
+                for (neighbor_hash, _neighbor_cell, _) in self.neighbors(1, entry.parent, entry.cell)
+                {
+                    if visited.contains(&neighbor_hash) {
+                        continue;
+                    }
+                    let Some(neighbor_entry) = occupied_entry(neighbor_hash) else {
+                        continue;
+                    };
+                    if cell_group(neighbor_entry) != this_group {
+                        continue;
+                    }
+                    visited.insert(neighbor_hash);
+                    stack.push(neighbor_hash);
+                }
+            }
+
+            clusters.push((cells, entities));
+        }
+
+        clusters
     }
 
     /// Find entities in this and neighboring cells, within `cell_radius`.
@@ -147,23 +400,28 @@ impl<P: GridPrecision, F: QueryFilter> SpatialHashMap<P, F> {
     /// cells. You can also think of this as a cube centered on the specified cell, expanded in each
     /// direction by `radius`.
     ///
+    /// Breaking change: `parent` (and the equivalent parameter on [`Self::neighbors_flattened`] and
+    /// [`Self::neighbors_flood`]) used to take `&Parent`; it now takes the parent's `Entity`
+    /// directly, for the same reason as [`SpatialHash::new`]. The newer queries
+    /// ([`Self::within_radius`], [`Self::within_aabb`], [`Self::get_exact`]) take an `Entity` too.
+    ///
     /// Returns an iterator over all non-empty neighboring cells, including the cell, and the set of
     /// entities in that cell.
-    pub fn neighbors<'a>(
-        &'a self,
+    pub fn neighbors(
+        &self,
         cell_radius: u8,
-        parent: &'a Parent,
+        parent: Entity,
         cell: GridCell<P>,
-    ) -> impl Iterator<Item = (SpatialHash<P>, GridCell<P>, &HashSet<Entity, PassHash>)> + 'a {
+    ) -> impl Iterator<Item = (SpatialHash<P>, GridCell<P>, &HashSet<Entity, PassHash>)> + '_ {
         let radius = cell_radius as i32;
         let search_width = 1 + 2 * radius;
         let search_volume = search_width.pow(3);
         let center = -radius;
         let hash = PartialSpatialHash::new(parent);
         (0..search_volume).filter_map(move |i| {
-            let x = center + i; //  % search_width.pow(0)
-            let y = center + i % search_width; // .pow(1)
-            let z = center + i % search_width.pow(2);
+            let x = center + i % search_width;
+            let y = center + (i / search_width) % search_width;
+            let z = center + (i / search_width.pow(2)) % search_width;
             let offset = IVec3::new(x, y, z);
             let neighbor_cell = cell + offset;
             let neighbor_hash = hash.generate(&neighbor_cell);
@@ -173,14 +431,86 @@ impl<P: GridPrecision, F: QueryFilter> SpatialHashMap<P, F> {
         })
     }
 
+    /// Find entities whose exact position is within `radius` of the point described by `cell` and
+    /// `local_offset`, in the reference frame's local units.
+    ///
+    /// This first converts `radius` into a conservative cell radius using `cell_edge_length` (the
+    /// reference frame's cell edge length), gathers candidate cells with [`Self::neighbors`], then
+    /// rejects any candidate entity whose exact `GridCell` and [`Transform`] translation place it
+    /// farther than `radius` from the query point. This gives a precise result, rather than the
+    /// coarse cube-of-cells returned by [`Self::neighbors`] alone.
+    ///
+    /// Note: this takes a `cell_edge_length` parameter rather than deriving it from a
+    /// `ReferenceFrame`, since [`SpatialHashMap`] has no handle back to the reference frame that
+    /// produced `cell` - the caller is expected to pass in `reference_frame.cell_edge_length()`.
+    pub fn within_radius(
+        &self,
+        parent: Entity,
+        cell: GridCell<P>,
+        local_offset: Vec3,
+        cell_edge_length: f32,
+        radius: f32,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        // The query point can be up to `local_offset` away from the cell's center, and a candidate
+        // entity can likewise sit up to one `cell_edge_length` away from its own cell's center, so
+        // both slack terms need to be folded into the radius before converting to a whole number of
+        // cells (rounding up covers the remaining fraction of a cell).
+        let padded_radius = radius + local_offset.length() + cell_edge_length;
+        let cell_radius = (padded_radius / cell_edge_length).ceil().max(0.0) as u8;
+        self.neighbors(cell_radius, parent, cell)
+            .flat_map(move |(hash, neighbor_cell, _)| {
+                let cell_offset = (neighbor_cell - cell).as_vec3() * cell_edge_length;
+                let entry = self.shard(hash).get(&hash).and_then(|bucket| {
+                    bucket
+                        .iter()
+                        .find(|entry| entry.parent == parent && entry.cell == neighbor_cell)
+                });
+                entry.into_iter().flat_map(move |entry| {
+                    entry
+                        .translations
+                        .iter()
+                        .filter_map(move |(&entity, &translation)| {
+                            let delta = cell_offset + translation - local_offset;
+                            (delta.length() <= radius).then_some(entity)
+                        })
+                })
+            })
+    }
+
+    /// Find entities inside the axis-aligned box of grid cells between `min` and `max`, inclusive.
+    pub fn within_aabb(
+        &self,
+        parent: Entity,
+        min: GridCell<P>,
+        max: GridCell<P>,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.values())
+            .flat_map(|bucket| bucket.iter())
+            .filter(move |entry| entry.parent == parent)
+            .flat_map(move |entry| {
+                let in_bounds = entry.cell.x >= min.x
+                    && entry.cell.x <= max.x
+                    && entry.cell.y >= min.y
+                    && entry.cell.y <= max.y
+                    && entry.cell.z >= min.z
+                    && entry.cell.z <= max.z;
+                in_bounds
+                    .then(|| entry.entities.iter().copied())
+                    .into_iter()
+                    .flatten()
+            })
+    }
+
     /// Like [`Self::neighbors`], but flattens the result, giving you a flat list of entities in
     /// neighboring cells.
-    pub fn neighbors_flattened<'a>(
-        &'a self,
+    pub fn neighbors_flattened(
+        &self,
         cell_radius: u8,
-        parent: &'a Parent,
+        parent: Entity,
         cell: GridCell<P>,
-    ) -> impl Iterator<Item = &Entity> + 'a {
+    ) -> impl Iterator<Item = &Entity> + '_ {
         self.neighbors(cell_radius, parent, cell)
             .flat_map(|(.., set)| set.iter())
     }
@@ -188,12 +518,12 @@ impl<P: GridPrecision, F: QueryFilter> SpatialHashMap<P, F> {
     /// Recursively searches for all connected neighboring cells within the given `cell_radius` at
     /// every point. The result is a set of all grid cells connected by a cell distance of
     /// `cell_radius` or less.
-    pub fn neighbors_flood<'a>(
-        &'a self,
+    pub fn neighbors_flood(
+        &self,
         cell_radius: u8,
-        parent: &'a Parent,
+        parent: Entity,
         cell: GridCell<P>,
-    ) -> HashMap<SpatialHash<P>, &'a HashSet<Entity, PassHash>, PassHash> {
+    ) -> HashMap<SpatialHash<P>, &HashSet<Entity, PassHash>, PassHash> {
         let mut stack = vec![cell];
         let mut result = HashMap::default();
         while let Some(cell) = stack.pop() {
@@ -210,24 +540,181 @@ impl<P: GridPrecision, F: QueryFilter> SpatialHashMap<P, F> {
     fn update_spatial_hash(
         mut commands: Commands,
         mut spatial: ResMut<SpatialHashMap<P>>,
+        mut cell_changed_events: EventWriter<CellChanged<P>>,
         changed_entities: Query<
-            (Entity, &Parent, &GridCell<P>, Option<&SpatialHash<P>>),
-            (Or<(Changed<Parent>, Changed<GridCell<P>>)>, F),
+            (Entity, &Parent, &GridCell<P>, &Transform, Option<&SpatialHash<P>>),
+            (Or<(Changed<Parent>, Changed<GridCell<P>>, Changed<Transform>)>, F),
         >,
     ) {
         // This simple sequential impl is faster than the parallel versions I've tried.
-        for (entity, parent, cell, old_hash) in &changed_entities {
+        for (entity, parent, cell, transform, old_hash) in &changed_entities {
+            let parent = parent.get();
             let spatial_hash = SpatialHash::new(parent, cell);
             // Although spatial.insert checks for equality as well, this check has a 40% savings in
             // cases where the grid cell is mutated (change detection triggered), but it has not
             // actually changed, this also helps if multiple plugins are updating the spatial hash,
-            // and it is already correct.
+            // and it is already correct. We still need to call `spatial.insert` even when the hash
+            // is unchanged, so that a within-cell `Transform` change updates the stored translation.
             if old_hash.ne(&Some(&spatial_hash)) {
                 commands.entity(entity).insert(spatial_hash);
-                spatial.insert(entity, spatial_hash);
+
+                if spatial.send_events {
+                    let old_cell = old_hash.and_then(|&old_hash| {
+                        spatial.shard(old_hash).get(&old_hash).and_then(|bucket| {
+                            bucket
+                                .iter()
+                                .find(|entry| entry.entities.contains(&entity))
+                                .map(|entry| (entry.cell, entry.parent))
+                        })
+                    });
+                    cell_changed_events.send(CellChanged {
+                        entity,
+                        old: old_hash.copied(),
+                        new: spatial_hash,
+                        old_cell,
+                        new_cell: (*cell, parent),
+                    });
+                }
             }
+            spatial.insert(entity, spatial_hash, *cell, parent, transform.translation);
         }
     }
+
+    /// Sharded, parallel equivalent of [`Self::update_spatial_hash`], used instead of it when the
+    /// `sharded_map` feature is enabled.
+    ///
+    /// Naively parallelizing inserts into a single `HashMap` is slower than the sequential version,
+    /// because every writer contends on the same map. Instead, this rehashes every changed entity
+    /// in parallel into thread-local buffers (no contention, since each thread only touches its own
+    /// buffer), partitions those buffers by destination shard, then merges each shard
+    /// independently and in parallel - shards never contend with each other, since each is only
+    /// ever touched by the one thread merging it.
+    #[cfg(feature = "sharded_map")]
+    fn update_spatial_hash_sharded(
+        mut commands: Commands,
+        mut spatial: ResMut<SpatialHashMap<P>>,
+        mut cell_changed_events: EventWriter<CellChanged<P>>,
+        changed_entities: Query<
+            (Entity, &Parent, &GridCell<P>, &Transform, Option<&SpatialHash<P>>),
+            (Or<(Changed<Parent>, Changed<GridCell<P>>, Changed<Transform>)>, F),
+        >,
+    ) {
+        use bevy_utils::Parallel;
+        use rayon::prelude::*;
+
+        // Phase 1: rehash every changed entity in parallel, bucketing each pending change into a
+        // thread-local buffer. No contention, since each thread only ever writes to its own buffer.
+        // We still need to record entities whose hash is unchanged, so a within-cell `Transform`
+        // change updates the stored translation, same as the sequential `update_spatial_hash`.
+        let buffers: Parallel<Vec<PendingChange<P>>> = Parallel::default();
+        changed_entities
+            .par_iter()
+            .for_each(|(entity, parent, cell, transform, old_hash)| {
+                let parent = parent.get();
+                let new_hash = SpatialHash::new(parent, cell);
+                buffers.borrow_local_mut().push(PendingChange {
+                    entity,
+                    parent,
+                    cell: *cell,
+                    translation: transform.translation,
+                    old_hash: old_hash.copied(),
+                    new_hash,
+                    hash_changed: old_hash.ne(&Some(&new_hash)),
+                });
+            });
+        let pending: Vec<PendingChange<P>> = buffers.into_iter().flatten().collect();
+
+        // Phase 2: remove moved entities from their old bucket, fire events, and insert the updated
+        // `SpatialHash` component. This still touches one shard at a time, but each change only
+        // touches a single bucket, so it is cheap relative to the rehashing in phase 1. Entities
+        // whose hash is unchanged skip all of this, same as the sequential path.
+        for change in pending.iter().filter(|change| change.hash_changed) {
+            commands.entity(change.entity).insert(change.new_hash);
+
+            if spatial.send_events {
+                let old_cell = change.old_hash.and_then(|old_hash| {
+                    spatial.shard(old_hash).get(&old_hash).and_then(|bucket| {
+                        bucket
+                            .iter()
+                            .find(|entry| entry.entities.contains(&change.entity))
+                            .map(|entry| (entry.cell, entry.parent))
+                    })
+                });
+                cell_changed_events.send(CellChanged {
+                    entity: change.entity,
+                    old: change.old_hash,
+                    new: change.new_hash,
+                    old_cell,
+                    new_cell: (change.cell, change.parent),
+                });
+            }
+
+            if let Some(old_hash) = change.old_hash {
+                if let Some(bucket) = spatial.shard_mut(old_hash).get_mut(&old_hash) {
+                    for entry in bucket.iter_mut() {
+                        entry.entities.remove(&change.entity);
+                        entry.translations.remove(&change.entity);
+                    }
+                    // Drop any entry that's now vacant, so it stops showing up as an occupied cell
+                    // in `clusters`, `get_exact`, `iter`, etc.
+                    bucket.retain(|entry| !entry.entities.is_empty());
+                }
+            }
+            spatial.reverse_map.insert(change.entity, change.new_hash);
+        }
+
+        // Phase 3: partition the pending inserts by destination shard, then merge every shard in
+        // parallel.
+        let mut by_shard: Vec<Vec<&PendingChange<P>>> =
+            (0..SHARD_COUNT).map(|_| Vec::new()).collect();
+        for change in &pending {
+            by_shard[Self::shard_index(change.new_hash)].push(change);
+        }
+
+        spatial
+            .shards
+            .par_iter_mut()
+            .zip(by_shard.into_par_iter())
+            .for_each(|(shard, changes)| {
+                for change in changes {
+                    let bucket = shard.entry(change.new_hash).or_default();
+                    if let Some(entry) = bucket
+                        .iter_mut()
+                        .find(|entry| entry.parent == change.parent && entry.cell == change.cell)
+                    {
+                        entry.entities.insert(change.entity);
+                        entry.translations.insert(change.entity, change.translation);
+                    } else {
+                        let mut entities = HashSet::with_hasher(PassHash);
+                        entities.insert(change.entity);
+                        let mut translations = HashMap::with_hasher(PassHash);
+                        translations.insert(change.entity, change.translation);
+                        bucket.push(CellEntry {
+                            entities,
+                            translations,
+                            cell: change.cell,
+                            parent: change.parent,
+                        });
+                    }
+                }
+            });
+    }
+}
+
+/// A single entity's pending spatial hash update, computed in phase 1 of
+/// [`SpatialHashMap::update_spatial_hash_sharded`] and applied in phases 2 and 3.
+#[cfg(feature = "sharded_map")]
+struct PendingChange<P: GridPrecision> {
+    entity: Entity,
+    parent: Entity,
+    cell: GridCell<P>,
+    translation: Vec3,
+    old_hash: Option<SpatialHash<P>>,
+    new_hash: SpatialHash<P>,
+    /// Whether `new_hash` actually differs from `old_hash`. Entities with an unchanged hash skip
+    /// phase 2 (the component insert, event, and old-bucket removal that only make sense for an
+    /// actual move), but still flow through phase 3 so their stored translation is refreshed.
+    hash_changed: bool,
 }
 
 /// A halfway-hashed [`SpatialHash`], only taking into account the parent, and not the cell. This
@@ -240,7 +727,10 @@ pub struct PartialSpatialHash<P: GridPrecision> {
 
 impl<P: GridPrecision> PartialSpatialHash<P> {
     /// Create a partial spatial hash from the parent of the hashed entity.
-    pub fn new(parent: &Parent) -> Self {
+    ///
+    /// Breaking change: this used to take `&Parent`; it now takes the parent's `Entity` directly
+    /// (e.g. `parent.get()`), for the same reason as [`SpatialHash::new`].
+    pub fn new(parent: Entity) -> Self {
         let mut hasher = AHasher::default();
         hasher.write_u64(parent.to_bits());
         PartialSpatialHash {
@@ -390,7 +880,8 @@ mod tests {
             .world_mut()
             .query::<&Parent>()
             .get(app.world(), entities.a)
-            .unwrap();
+            .unwrap()
+            .get();
 
         let map = app.world().resource::<SpatialHashMap<i32>>();
         let neighbors: HashSet<Entity> = map
@@ -412,4 +903,335 @@ mod tests {
         assert!(flooded.contains(&entities.b));
         assert!(flooded.contains(&entities.c));
     }
+
+    #[test]
+    fn clusters() {
+        use bevy::prelude::*;
+
+        #[derive(Resource, Clone)]
+        struct Entities {
+            a: Entity,
+            b: Entity,
+            c: Entity,
+        }
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                // `a` and `b` are adjacent, `c` is far away on its own.
+                let a = root.spawn_spatial(GridCell::new(0, 0, 0)).id();
+                let b = root.spawn_spatial(GridCell::new(1, 1, 1)).id();
+                let c = root.spawn_spatial(GridCell::new(100, 100, 100)).id();
+
+                root.commands().insert_resource(Entities { a, b, c });
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update();
+
+        let entities = app.world().resource::<Entities>().clone();
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+        let clusters = map.clusters();
+
+        assert_eq!(clusters.len(), 2, "two disconnected clusters");
+
+        let (_, big) = clusters
+            .iter()
+            .find(|(_, set)| set.len() == 2)
+            .expect("cluster of two connected cells");
+        assert!(big.contains(&entities.a));
+        assert!(big.contains(&entities.b));
+
+        let (_, lone) = clusters
+            .iter()
+            .find(|(_, set)| set.len() == 1)
+            .expect("cluster of the lone cell");
+        assert!(lone.contains(&entities.c));
+    }
+
+    #[test]
+    fn clusters_face_adjacent() {
+        use bevy::prelude::*;
+
+        #[derive(Resource, Clone)]
+        struct Entities {
+            a: Entity,
+            b: Entity,
+        }
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                // `a` and `b` are one step apart on a single axis (face-adjacent, not diagonal), and
+                // must still merge into a single 26-connected cluster.
+                let a = root.spawn_spatial(GridCell::new(0, 0, 0)).id();
+                let b = root.spawn_spatial(GridCell::new(1, 0, 0)).id();
+
+                root.commands().insert_resource(Entities { a, b });
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update();
+
+        let entities = app.world().resource::<Entities>().clone();
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+        let clusters = map.clusters();
+
+        assert_eq!(clusters.len(), 1, "face-adjacent cells must merge into one cluster");
+        let (_, cluster) = &clusters[0];
+        assert!(cluster.contains(&entities.a));
+        assert!(cluster.contains(&entities.b));
+    }
+
+    #[test]
+    fn clusters_after_move() {
+        use bevy::prelude::*;
+
+        #[derive(Resource, Clone)]
+        struct Entities {
+            a: Entity,
+            b: Entity,
+        }
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                // `a` and `b` start adjacent, so they form a single cluster.
+                let a = root.spawn_spatial(GridCell::new(0, 0, 0)).id();
+                let b = root.spawn_spatial(GridCell::new(1, 1, 1)).id();
+
+                root.commands().insert_resource(Entities { a, b });
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update();
+
+        let entities = app.world().resource::<Entities>().clone();
+
+        // Move `b` far away, vacating its old cell.
+        *app.world_mut().get_mut::<GridCell<i32>>(entities.b).unwrap() =
+            GridCell::new(100, 100, 100);
+
+        app.update();
+
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+        let clusters = map.clusters();
+
+        assert_eq!(
+            clusters.len(),
+            2,
+            "the vacated cell must not linger as a spurious empty cluster"
+        );
+        assert!(clusters.iter().all(|(cells, set)| {
+            !cells.is_empty() && !set.is_empty()
+        }));
+    }
+
+    #[test]
+    fn within_radius_and_aabb() {
+        use bevy::prelude::*;
+
+        #[derive(Resource, Clone)]
+        struct Entities {
+            a: Entity,
+            b: Entity,
+            c: Entity,
+        }
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                let a = root.spawn_spatial(GridCell::new(0, 0, 0)).id();
+                let b = root.spawn_spatial(GridCell::new(1, 0, 0)).id();
+                let c = root.spawn_spatial(GridCell::new(10, 0, 0)).id();
+
+                root.commands().insert_resource(Entities { a, b, c });
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update();
+
+        let entities = app.world().resource::<Entities>().clone();
+        let parent = app
+            .world_mut()
+            .query::<&Parent>()
+            .get(app.world(), entities.a)
+            .unwrap()
+            .get();
+
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+
+        let cell_edge_length = 1.0;
+        let nearby: HashSet<Entity> = map
+            .within_radius(parent, GridCell::ZERO, Vec3::ZERO, cell_edge_length, 1.5)
+            .collect();
+
+        assert!(nearby.contains(&entities.a));
+        assert!(nearby.contains(&entities.b));
+        assert!(!nearby.contains(&entities.c));
+
+        let boxed: HashSet<Entity> = map
+            .within_aabb(parent, GridCell::new(0, 0, 0), GridCell::new(2, 0, 0))
+            .collect();
+
+        assert!(boxed.contains(&entities.a));
+        assert!(boxed.contains(&entities.b));
+        assert!(!boxed.contains(&entities.c));
+    }
+
+    #[test]
+    fn within_radius_after_move() {
+        use bevy::prelude::*;
+
+        #[derive(Resource, Clone)]
+        struct TheEntity(Entity);
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                let entity = root.spawn_spatial(GridCell::new(0, 0, 0)).id();
+                root.commands().insert_resource(TheEntity(entity));
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update();
+
+        let entity = app.world().resource::<TheEntity>().clone().0;
+        let parent = app
+            .world_mut()
+            .query::<&Parent>()
+            .get(app.world(), entity)
+            .unwrap()
+            .get();
+
+        let cell_edge_length = 1.0;
+
+        // A `Transform`-only move within the same cell must refresh the stored translation used by
+        // `within_radius`, not just the `GridCell`/`SpatialHash`.
+        app.world_mut().get_mut::<Transform>(entity).unwrap().translation = Vec3::new(0.4, 0.0, 0.0);
+
+        app.update();
+
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+        let nearby: HashSet<Entity> = map
+            .within_radius(parent, GridCell::ZERO, Vec3::new(0.4, 0.0, 0.0), cell_edge_length, 0.1)
+            .collect();
+
+        assert!(
+            nearby.contains(&entity),
+            "within_radius must see the refreshed translation, not a stale one"
+        );
+    }
+
+    #[test]
+    fn get_exact() {
+        use bevy::prelude::*;
+
+        #[derive(Resource, Clone)]
+        struct Entities {
+            a: Entity,
+        }
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                let a = root.spawn_spatial(GridCell::new(0, 1, 2)).id();
+
+                root.commands().insert_resource(Entities { a });
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update();
+
+        let entities = app.world().resource::<Entities>().clone();
+        let parent = app
+            .world_mut()
+            .query::<&Parent>()
+            .get(app.world(), entities.a)
+            .unwrap()
+            .get();
+
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+
+        let exact = map
+            .get_exact(parent, &GridCell::new(0, 1, 2))
+            .expect("occupied cell");
+        assert!(exact.contains(&entities.a));
+
+        assert!(map.get_exact(parent, &GridCell::new(0, 1, 3)).is_none());
+        drop(map);
+
+        // Vacate the cell by moving the entity elsewhere.
+        *app.world_mut().get_mut::<GridCell<i32>>(entities.a).unwrap() = GridCell::new(0, 1, 3);
+
+        app.update();
+
+        let map = app.world().resource::<SpatialHashMap<i32>>();
+        assert!(
+            map.get_exact(parent, &GridCell::new(0, 1, 2)).is_none(),
+            "a vacated cell must read as None, not an empty set"
+        );
+    }
+
+    #[test]
+    fn cell_changed_events() {
+        use bevy::prelude::*;
+
+        use crate::spatial_hash::CellChanged;
+
+        #[derive(Resource, Clone)]
+        struct TheEntity(Entity);
+
+        let setup = |mut commands: Commands| {
+            commands.spawn_big_space(ReferenceFrame::<i32>::default(), |root| {
+                let entity = root.spawn_spatial(GridCell::new(0, 0, 0)).id();
+                root.commands().insert_resource(TheEntity(entity));
+            });
+        };
+
+        let mut app = App::new();
+        app.add_plugins(SpatialHashPlugin::<i32>::default())
+            .add_systems(Update, setup);
+
+        app.update(); // Spawn the entity, and fire the initial `CellChanged` event.
+
+        app.world_mut()
+            .resource_mut::<Events<CellChanged<i32>>>()
+            .clear();
+
+        let entity = app.world().resource::<TheEntity>().clone().0;
+        *app.world_mut().get_mut::<GridCell<i32>>(entity).unwrap() = GridCell::new(1, 0, 0);
+
+        app.update();
+
+        let mut events = app
+            .world_mut()
+            .resource_mut::<Events<CellChanged<i32>>>();
+        let mut reader = events.get_cursor();
+        let event = reader
+            .read(&events)
+            .find(|event| event.entity == entity)
+            .expect("a CellChanged event for the moved entity");
+
+        assert_eq!(event.old_cell.unwrap().0, GridCell::new(0, 0, 0));
+        assert_eq!(event.new_cell.0, GridCell::new(1, 0, 0));
+    }
 }